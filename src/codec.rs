@@ -0,0 +1,170 @@
+//! Compact binary (de)serialization shared by tree state and proofs.
+
+use crate::Hash;
+use std::fmt::{self, Display, Formatter};
+
+/// Failure decoding a hash, proof, or saved tree state.
+#[derive(Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    InvalidHex,
+    InvalidBase64,
+    InvalidLength,
+    UnexpectedEnd,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let msg = match self {
+            DecodeError::InvalidHex => "invalid hex encoding",
+            DecodeError::InvalidBase64 => "invalid base64 encoding",
+            DecodeError::InvalidLength => "decoded bytes have the wrong length for this hash",
+            DecodeError::UnexpectedEnd => "unexpected end of encoded data",
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// `[len: u32 LE][bytes]`, the inverse of `decode_hash`.
+pub(crate) fn encode_hash<H: Hash>(item: &H, out: &mut Vec<u8>) {
+    let bytes = item.to_bytes();
+    out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(&bytes);
+}
+
+/// `[count: u64 LE] ([len: u32 LE][bytes])*`
+pub(crate) fn encode_hashes<H: Hash>(items: &[H]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(items.len() as u64).to_le_bytes());
+    for item in items {
+        encode_hash(item, &mut out);
+    }
+    out
+}
+
+/// Decode a `[count: u64 LE][item]*` list off `cursor`, where each item is
+/// decoded by `decode_one`. Shared by every list-of-items wire format in this
+/// crate (hash vecs, proof siblings) so the "don't trust `count`" handling
+/// below only has to be written once.
+///
+/// `count` is unvalidated attacker input, and a tiny buffer can claim
+/// billions of items, so this never pre-allocates off it: the vec grows only
+/// as items are actually decoded, which `cursor.take` bounds to the real
+/// buffer size.
+pub(crate) fn decode_items<'a, T>(
+    cursor: &mut Cursor<'a>,
+    mut decode_one: impl FnMut(&mut Cursor<'a>) -> Result<T, DecodeError>,
+) -> Result<Vec<T>, DecodeError> {
+    let count = cursor.take_u64()?;
+    let mut items = Vec::new();
+    for _ in 0..count {
+        items.push(decode_one(cursor)?);
+    }
+    Ok(items)
+}
+
+/// Decode a `[len: u32 LE][bytes]` hash, rejecting a length that doesn't
+/// match `H`'s real output size instead of handing a mismatched slice to
+/// `H::from_bytes` (which may panic, e.g. `DigestHash`'s `GenericArray`).
+pub(crate) fn decode_hash<H: Hash>(cursor: &mut Cursor) -> Result<H, DecodeError> {
+    let expected_len = H::default().to_bytes().len();
+    let len = cursor.take_u32()? as usize;
+    if len != expected_len {
+        return Err(DecodeError::InvalidLength);
+    }
+    Ok(H::from_bytes(cursor.take(len)?))
+}
+
+pub(crate) fn decode_hash_vec<H: Hash>(cursor: &mut Cursor) -> Result<Vec<H>, DecodeError> {
+    decode_items(cursor, decode_hash)
+}
+
+#[cfg(test)]
+fn decode_hashes<H: Hash>(bytes: &[u8]) -> Result<Vec<H>, DecodeError> {
+    decode_hash_vec(&mut Cursor::new(bytes))
+}
+
+pub(crate) struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Cursor { bytes, pos: 0 }
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        let end = self.pos + len;
+        let slice = self.bytes.get(self.pos..end).ok_or(DecodeError::UnexpectedEnd)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    pub(crate) fn take_u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn take_u32(&mut self) -> Result<u32, DecodeError> {
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    pub(crate) fn take_u64(&mut self) -> Result<u64, DecodeError> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SHA256;
+
+    #[test]
+    fn hash_vec_roundtrips() {
+        let items: Vec<SHA256> = ["a", "b", "c"]
+            .iter()
+            .map(|v| SHA256::hash_leaf(v.as_bytes()))
+            .collect();
+
+        let encoded = encode_hashes(&items);
+        let decoded: Vec<SHA256> = decode_hashes(&encoded).unwrap();
+        assert_eq!(items, decoded);
+    }
+
+    #[test]
+    fn decode_rejects_truncated_input() {
+        let items: Vec<SHA256> = vec![SHA256::hash_leaf(b"a")];
+        let mut encoded = encode_hashes(&items);
+        encoded.truncate(encoded.len() - 1);
+
+        let result: Result<Vec<SHA256>, _> = decode_hashes(&encoded);
+        assert_eq!(result, Err(DecodeError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn decode_rejects_mismatched_item_length() {
+        let items: Vec<SHA256> = vec![SHA256::hash_leaf(b"a")];
+        let mut encoded = encode_hashes(&items);
+        // Shrink the length prefix of the one item from 32 to 4, leaving the
+        // stored bytes untouched.
+        encoded[8..12].copy_from_slice(&4u32.to_le_bytes());
+
+        let result: Result<Vec<SHA256>, _> = decode_hashes(&encoded);
+        assert_eq!(result, Err(DecodeError::InvalidLength));
+    }
+
+    #[test]
+    fn decode_rejects_huge_claimed_count_without_allocating_it() {
+        // Nine bytes: a `count` of 200 million items, no item data at all.
+        let mut malicious = (200_000_000u64).to_le_bytes().to_vec();
+        malicious.push(0);
+
+        let result: Result<Vec<SHA256>, _> = decode_hashes(&malicious);
+        assert_eq!(result, Err(DecodeError::UnexpectedEnd));
+    }
+}
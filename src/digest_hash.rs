@@ -0,0 +1,125 @@
+//! Generic [`Hash`] impl backed by any `digest::Digest` algorithm, so new
+//! hash functions (SHA-384, SHA-512, BLAKE2, ...) need no hand-written
+//! `Hash`/`Display`/`Debug` boilerplate.
+
+use crate::{DecodeError, Hash};
+use digest::generic_array::GenericArray;
+use digest::Digest;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::marker::PhantomData;
+
+/// A Merkle [`Hash`] wrapping any `digest::Digest` algorithm `D`.
+pub struct DigestHash<D: Digest>(pub(crate) GenericArray<u8, D::OutputSize>, PhantomData<D>);
+
+impl<D: Digest> Clone for DigestHash<D> {
+    fn clone(&self) -> Self {
+        DigestHash(self.0.clone(), PhantomData)
+    }
+}
+
+impl<D: Digest> Eq for DigestHash<D> {}
+
+impl<D: Digest> PartialEq for DigestHash<D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<D: Digest> Default for DigestHash<D> {
+    fn default() -> Self {
+        DigestHash(GenericArray::default(), PhantomData)
+    }
+}
+
+impl<D: Digest> Hash for DigestHash<D> {
+    fn hash_leaf(data: &[u8]) -> Self {
+        let mut buf = Vec::with_capacity(1 + data.len());
+        buf.push(0x00);
+        buf.extend_from_slice(data);
+        DigestHash(D::digest(&buf), PhantomData)
+    }
+
+    fn hash_nodes(left: &Self, right: &Self) -> Self {
+        let mut buf = Vec::with_capacity(1 + left.0.len() + right.0.len());
+        buf.push(0x01);
+        buf.extend_from_slice(&left.0);
+        buf.extend_from_slice(&right.0);
+        DigestHash(D::digest(&buf), PhantomData)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        DigestHash(GenericArray::clone_from_slice(bytes), PhantomData)
+    }
+}
+
+impl<D: Digest> DigestHash<D> {
+    /// Lowercase hex encoding of the digest, e.g. for display or wire
+    /// transmission.
+    pub fn to_hex(&self) -> String {
+        let mut hex = String::with_capacity(self.0.len() * 2);
+        for byte in self.0.iter() {
+            hex.push_str(&format!("{:02x}", byte));
+        }
+        hex
+    }
+
+    /// Inverse of `to_hex`. Accepts an optional `0x` prefix.
+    pub fn from_hex(s: &str) -> Result<Self, DecodeError> {
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        // Slicing by byte index below assumes one byte per hex digit, which
+        // only holds for ASCII; reject anything else up front instead of
+        // risking a non-char-boundary slice panic.
+        if !s.is_ascii() || !s.len().is_multiple_of(2) {
+            return Err(DecodeError::InvalidHex);
+        }
+
+        let digits = s.as_bytes();
+        let mut bytes = Vec::with_capacity(digits.len() / 2);
+        for pair in digits.chunks_exact(2) {
+            let pair = std::str::from_utf8(pair).expect("checked ascii above");
+            let byte = u8::from_str_radix(pair, 16).map_err(|_| DecodeError::InvalidHex)?;
+            bytes.push(byte);
+        }
+
+        Self::from_checked_bytes(&bytes)
+    }
+
+    /// Standard base64 encoding of the digest.
+    pub fn to_base64(&self) -> String {
+        base64::encode(&self.0)
+    }
+
+    /// Inverse of `to_base64`.
+    pub fn from_base64(s: &str) -> Result<Self, DecodeError> {
+        let bytes = base64::decode(s).map_err(|_| DecodeError::InvalidBase64)?;
+        Self::from_checked_bytes(&bytes)
+    }
+
+    fn from_checked_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        // `<D as Digest>::output_size()`, not the inherent/blanket form:
+        // newer `digest` releases also bring a supertrait `output_size`
+        // into scope, which makes an unqualified call ambiguous.
+        if bytes.len() != <D as Digest>::output_size() {
+            return Err(DecodeError::InvalidLength);
+        }
+        Ok(Self::from_bytes(bytes))
+    }
+}
+
+impl<D: Digest> Display for DigestHash<D> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let mut hex = String::new();
+        hex.extend(self.0.iter().map(|byte| format!("{:02x?}", byte)));
+        write!(f, "0x{}", hex)
+    }
+}
+
+impl<D: Digest> Debug for DigestHash<D> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,195 @@
+//! Fixed-depth, append-only Merkle tree that keeps only an O(depth) frontier,
+//! in the spirit of Zcash's incrementalmerkletree.
+
+use crate::Hash;
+
+/// Precompute the hashes of empty subtrees of every height from `0` (a
+/// single empty leaf) up to `depth` (the whole empty tree), for use as the
+/// `empty_roots` table passed to [`IncrementalMerkleTree::new`].
+pub fn empty_roots<H: Hash>(depth: usize, empty_leaf: H) -> Vec<H> {
+    let mut roots = Vec::with_capacity(depth + 1);
+    roots.push(empty_leaf);
+    for i in 0..depth {
+        let prev = roots[i].clone();
+        roots.push(H::hash_nodes(&prev, &prev));
+    }
+    roots
+}
+
+/// An append-only Merkle tree of fixed `depth`, storing only the frontier
+/// (the rightmost filled node at each level) instead of the full leaf set.
+pub struct IncrementalMerkleTree<H: Hash> {
+    depth: usize,
+    empty_roots: Vec<H>,
+    frontier: Vec<Option<H>>,
+    count: usize,
+    last_leaf: Option<H>,
+    last_ommers: Vec<H>,
+}
+
+impl<H: Hash> IncrementalMerkleTree<H> {
+    /// `empty_roots` must have `depth + 1` entries, as produced by
+    /// [`empty_roots`].
+    pub fn new(depth: usize, empty_roots: Vec<H>) -> Self {
+        assert_eq!(
+            empty_roots.len(),
+            depth + 1,
+            "empty_roots must have depth + 1 entries"
+        );
+
+        IncrementalMerkleTree {
+            depth,
+            empty_roots,
+            frontier: vec![None; depth],
+            count: 0,
+            last_leaf: None,
+            last_ommers: vec![],
+        }
+    }
+
+    /// Number of leaves appended so far.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Place `leaf` at the next free position, updating the frontier.
+    pub fn append(&mut self, leaf: H) {
+        assert!(self.count < (1usize << self.depth), "tree is full");
+
+        let mut node = leaf.clone();
+        let mut index = self.count;
+        let mut ommers = vec![];
+
+        for level in 0..self.depth {
+            if index & 1 == 0 {
+                self.frontier[level] = Some(node);
+                break;
+            }
+
+            let left = self.frontier[level]
+                .take()
+                .expect("frontier missing left sibling for a filled position");
+            ommers.push(left.clone());
+            node = H::hash_nodes(&left, &node);
+            index >>= 1;
+        }
+
+        self.last_leaf = Some(leaf);
+        self.last_ommers = ommers;
+        self.count += 1;
+    }
+
+    /// Authentication path for the most recently appended leaf, one sibling
+    /// per level from the leaf up to the root.
+    pub fn witness(&self) -> Vec<H> {
+        assert!(self.count > 0, "no leaf has been appended yet");
+
+        // Siblings for levels consumed while bubbling up the latest append
+        // come from the ommers captured during that append; siblings for
+        // levels above where the bubbling stopped are still sitting
+        // untouched in the frontier.
+        let position = self.count - 1;
+        let mut siblings = vec![];
+        let mut ommers = self.last_ommers.iter();
+
+        for level in 0..self.depth {
+            if (position >> level) & 1 == 0 {
+                siblings.push(self.empty_roots[level].clone());
+            } else if let Some(ommer) = ommers.next() {
+                siblings.push(ommer.clone());
+            } else {
+                siblings.push(
+                    self.frontier[level]
+                        .clone()
+                        .expect("frontier missing sibling for a filled level"),
+                );
+            }
+        }
+
+        siblings
+    }
+
+    /// Current root, computed from the frontier and the empty-subtree table.
+    pub fn root(&self) -> H {
+        match &self.last_leaf {
+            None => self.empty_roots[self.depth].clone(),
+            Some(leaf) => Self::fold(leaf.clone(), &self.witness(), self.count - 1),
+        }
+    }
+
+    /// Recompute the root `leaf` would produce given its authentication
+    /// `path` and `position`, and compare it against `root`.
+    pub fn verify_witness(leaf: H, path: &[H], position: usize, root: &H) -> bool {
+        &Self::fold(leaf, path, position) == root
+    }
+
+    fn fold(leaf: H, path: &[H], position: usize) -> H {
+        let mut node = leaf;
+        for (level, sibling) in path.iter().enumerate() {
+            node = if (position >> level) & 1 == 1 {
+                H::hash_nodes(sibling, &node)
+            } else {
+                H::hash_nodes(&node, sibling)
+            };
+        }
+        node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SHA256;
+
+    fn tree(depth: usize) -> IncrementalMerkleTree<SHA256> {
+        IncrementalMerkleTree::new(depth, empty_roots(depth, SHA256::default()))
+    }
+
+    #[test]
+    fn witness_verifies_after_each_append() {
+        let mut t = tree(4);
+        let leaves: Vec<SHA256> = (0u8..10)
+            .map(|i| SHA256::hash_leaf(&[i]))
+            .collect();
+
+        for (position, leaf) in leaves.iter().enumerate() {
+            t.append(leaf.clone());
+            let path = t.witness();
+            let root = t.root();
+            assert!(IncrementalMerkleTree::verify_witness(
+                leaf.clone(),
+                &path,
+                position,
+                &root
+            ));
+        }
+    }
+
+    #[test]
+    fn empty_tree_root_matches_empty_roots_table() {
+        let depth = 3;
+        let table = empty_roots(depth, SHA256::default());
+        let t = tree(depth);
+        assert_eq!(t.root(), table[depth]);
+    }
+
+    #[test]
+    fn witness_fails_for_wrong_leaf() {
+        let mut t = tree(4);
+        t.append(SHA256::hash_leaf(b"a"));
+        t.append(SHA256::hash_leaf(b"b"));
+
+        let path = t.witness();
+        let root = t.root();
+        assert!(!IncrementalMerkleTree::verify_witness(
+            SHA256::hash_leaf(b"a"),
+            &path,
+            1,
+            &root
+        ));
+    }
+}
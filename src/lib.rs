@@ -10,7 +10,7 @@
 //! let list = [
 //!    "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n",
 //! ];
-//! let hashed_list: Vec<SHA256> = list.iter().map(|v| SHA256::hash(v.as_bytes())).collect();
+//! let hashed_list: Vec<SHA256> = list.iter().map(|v| SHA256::hash_leaf(v.as_bytes())).collect();
 //! let mut better_merkle = BetterMerkleTreeSHA256::new();
 //!
 //! for i in 0..hashed_list.len() {
@@ -20,16 +20,37 @@
 //! }
 //! ```
 
-use sha2::Sha256;
-use sha3::{Digest, Sha3_256};
-use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use sha2::{Sha256, Sha384, Sha512};
+use sha3::Sha3_256;
 use std::marker::PhantomData;
 
-/// trait to define different hash function
+mod codec;
+mod digest_hash;
+mod incremental;
+mod proof;
+mod versioned;
+pub use codec::DecodeError;
+pub use digest_hash::DigestHash;
+pub use incremental::{empty_roots, IncrementalMerkleTree};
+pub use proof::{MerkleProof, Side};
+pub use versioned::VersionedMerkleTree;
+
+/// trait to define different hash function.
+///
+/// Leaves and internal nodes are hashed with distinct domains (RFC 6962 /
+/// Tendermint style: `0x00 || data` for leaves, `0x01 || left || right` for
+/// nodes) so a leaf can never collide with an internal node's preimage.
 pub trait Hash: Default + Clone + Eq + PartialEq {
-    fn hash(data: &[u8]) -> Self;
+    fn hash_leaf(data: &[u8]) -> Self;
 
-    fn to_string(hash: &Self) -> String;
+    fn hash_nodes(left: &Self, right: &Self) -> Self;
+
+    /// Raw digest bytes, for persisting or transmitting a hash.
+    fn to_bytes(&self) -> Vec<u8>;
+
+    /// Inverse of `to_bytes`. `bytes` must be exactly as long as this hash's
+    /// own output.
+    fn from_bytes(bytes: &[u8]) -> Self;
 }
 
 /// Traditional merkle tree.
@@ -55,9 +76,7 @@ impl<H: Hash> MerkleTree<H> {
         }
 
         for i in 0..r {
-            let mut s1 = H::to_string(&vec[i * 2]);
-            s1.push_str(&H::to_string(&vec[i * 2 + 1]));
-            next.push(H::hash(s1.as_bytes()))
+            next.push(H::hash_nodes(&vec[i * 2], &vec[i * 2 + 1]))
         }
 
         MerkleTree::merkle(next)
@@ -98,24 +117,22 @@ impl<H: Hash> BetterMerkleTree<H> {
         }
 
         let next = if self.0[round] != H::default() {
-            let mut s1 = H::to_string(&self.0[round]);
-            s1.push_str(&H::to_string(&new));
+            let hash = H::hash_nodes(&self.0[round], &new);
 
             if is_full {
                 self.0[round] = H::default();
                 next_full = true;
             }
 
-            H::hash(s1.as_bytes())
+            hash
         } else {
-            let mut s1 = H::to_string(&new);
-            s1.push_str(&s1.clone());
+            let hash = H::hash_nodes(&new, &new);
 
             if is_full {
                 self.0[round] = new;
             }
 
-            H::hash(s1.as_bytes())
+            hash
         };
 
         self.merkle(next, next_full, round + 1)
@@ -147,75 +164,31 @@ impl<H: Hash> BetterMerkleTree<H> {
         self.1 = hash.clone();
         hash
     }
-}
-
-/// helper SHA256
-#[derive(Default, Clone, Eq, PartialEq)]
-pub struct SHA256([u8; 32]);
 
-impl Hash for SHA256 {
-    fn hash(data: &[u8]) -> Self {
-        let mut h: SHA256 = Default::default();
-        let mut hasher = Sha256::new();
-        hasher.input(data);
-        h.0.copy_from_slice(&hasher.result()[..]);
-        h
+    /// Persist the state machine's frontier and current root to a compact
+    /// binary encoding, to be restored later with `from_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = codec::encode_hashes(&self.0);
+        codec::encode_hash(&self.1, &mut out);
+        out
     }
 
-    fn to_string(hash: &Self) -> String {
-        format!("{}", hash)
+    /// Inverse of `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut cursor = codec::Cursor::new(bytes);
+        let frontier = codec::decode_hash_vec(&mut cursor)?;
+        let root = codec::decode_hash(&mut cursor)?;
+        Ok(BetterMerkleTree(frontier, root))
     }
 }
 
-impl Display for SHA256 {
-    fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        let mut hex = String::new();
-        hex.extend(self.0.iter().map(|byte| format!("{:02x?}", byte)));
-        write!(f, "0x{}", hex)
-    }
-}
+/// SHA-256, backed by the generic [`DigestHash`] wrapper. Kept as a named
+/// alias for backward compatibility.
+pub type SHA256 = DigestHash<Sha256>;
 
-impl Debug for SHA256 {
-    fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        let mut hex = String::new();
-        hex.extend(self.0.iter().map(|byte| format!("{:02x?}", byte)));
-        write!(f, "0x{}", hex)
-    }
-}
-
-/// helper Keccak256(SHA3)
-#[derive(Default, Clone, Eq, PartialEq)]
-pub struct Keccak256([u8; 32]);
-
-impl Hash for Keccak256 {
-    fn hash(data: &[u8]) -> Self {
-        let mut h: Keccak256 = Default::default();
-        let mut hasher = Sha3_256::new();
-        hasher.input(data);
-        h.0.copy_from_slice(&hasher.result()[..]);
-        h
-    }
-
-    fn to_string(hash: &Self) -> String {
-        format!("{}", hash)
-    }
-}
-
-impl Display for Keccak256 {
-    fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        let mut hex = String::new();
-        hex.extend(self.0.iter().map(|byte| format!("{:02x?}", byte)));
-        write!(f, "0x{}", hex)
-    }
-}
-
-impl Debug for Keccak256 {
-    fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        let mut hex = String::new();
-        hex.extend(self.0.iter().map(|byte| format!("{:02x?}", byte)));
-        write!(f, "0x{}", hex)
-    }
-}
+/// Keccak256(SHA3), backed by the generic [`DigestHash`] wrapper. Kept as a
+/// named alias for backward compatibility.
+pub type Keccak256 = DigestHash<Sha3_256>;
 
 pub type MerkleTreeSHA256 = MerkleTree<SHA256>;
 pub type BetterMerkleTreeSHA256 = BetterMerkleTree<SHA256>;
@@ -223,19 +196,24 @@ pub type BetterMerkleTreeSHA256 = BetterMerkleTree<SHA256>;
 pub type MerkleTreeKeccak256 = MerkleTree<Keccak256>;
 pub type BetterMerkleTreeKeccak256 = BetterMerkleTree<Keccak256>;
 
+pub type MerkleTreeSha384 = MerkleTree<DigestHash<Sha384>>;
+pub type MerkleTreeSha512 = MerkleTree<DigestHash<Sha512>>;
+
 #[cfg(test)]
 mod tests {
     use super::{
-        BetterMerkleTreeKeccak256, BetterMerkleTreeSHA256, Hash, Keccak256, MerkleTreeKeccak256,
-        MerkleTreeSHA256, SHA256,
+        BetterMerkleTreeKeccak256, BetterMerkleTreeSHA256, DecodeError, DigestHash, Hash, Keccak256,
+        MerkleTreeKeccak256, MerkleTreeSHA256, MerkleTreeSha384, MerkleTreeSha512, Sha256, SHA256,
     };
+    use digest::Digest;
+    use sha2::{Sha384, Sha512};
 
     #[test]
     fn test_sha256() {
         let list = [
             "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n",
         ];
-        let hashed_list: Vec<SHA256> = list.iter().map(|v| SHA256::hash(v.as_bytes())).collect();
+        let hashed_list: Vec<SHA256> = list.iter().map(|v| SHA256::hash_leaf(v.as_bytes())).collect();
         let mut better_merkle = BetterMerkleTreeSHA256::new();
 
         for i in 0..hashed_list.len() {
@@ -251,7 +229,7 @@ mod tests {
             "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n",
         ];
         let hashed_list: Vec<Keccak256> =
-            list.iter().map(|v| Keccak256::hash(v.as_bytes())).collect();
+            list.iter().map(|v| Keccak256::hash_leaf(v.as_bytes())).collect();
         let mut better_merkle = BetterMerkleTreeKeccak256::new();
 
         for i in 0..hashed_list.len() {
@@ -260,4 +238,95 @@ mod tests {
             assert_eq!(root1, root2);
         }
     }
+
+    #[test]
+    fn root_differs_from_old_hex_concat_scheme() {
+        let a = SHA256::hash_leaf(b"a");
+        let b = SHA256::hash_leaf(b"b");
+        let root = MerkleTreeSHA256::root(vec![a.clone(), b.clone()]);
+
+        // The old scheme hashed the UTF-8 hex of the two children with no
+        // domain prefix: sha256(to_string(a) + to_string(b)).
+        let mut s = format!("{}", a);
+        s.push_str(&format!("{}", b));
+        let mut hasher = Sha256::new();
+        hasher.input(s.as_bytes());
+        let mut old_root = [0u8; 32];
+        old_root.copy_from_slice(&hasher.result()[..]);
+
+        assert_ne!(&root.0[..], &old_root[..]);
+    }
+
+    #[test]
+    fn forged_leaf_matching_node_preimage_does_not_verify() {
+        let a = SHA256::hash_leaf(b"a");
+        let b = SHA256::hash_leaf(b"b");
+        let node = SHA256::hash_nodes(&a, &b);
+
+        let mut preimage = vec![];
+        preimage.extend_from_slice(&a.0);
+        preimage.extend_from_slice(&b.0);
+        let forged_leaf = SHA256::hash_leaf(&preimage);
+
+        assert_ne!(forged_leaf, node);
+    }
+
+    #[test]
+    fn sha384_and_sha512_aliases_produce_a_root() {
+        let sha384_list: Vec<DigestHash<Sha384>> = ["a", "b", "c"]
+            .iter()
+            .map(|v| DigestHash::<Sha384>::hash_leaf(v.as_bytes()))
+            .collect();
+        let sha512_list: Vec<DigestHash<Sha512>> = ["a", "b", "c"]
+            .iter()
+            .map(|v| DigestHash::<Sha512>::hash_leaf(v.as_bytes()))
+            .collect();
+
+        let root384 = MerkleTreeSha384::root(sha384_list.clone());
+        let root512 = MerkleTreeSha512::root(sha512_list.clone());
+
+        assert_eq!(root384, MerkleTreeSha384::root(sha384_list));
+        assert_eq!(root512, MerkleTreeSha512::root(sha512_list));
+    }
+
+    #[test]
+    fn hash_roundtrips_through_hex_and_base64() {
+        let hash = SHA256::hash_leaf(b"a");
+
+        assert_eq!(SHA256::from_hex(&hash.to_hex()).unwrap(), hash);
+        assert_eq!(SHA256::from_hex(&format!("0x{}", hash.to_hex())).unwrap(), hash);
+        assert_eq!(SHA256::from_base64(&hash.to_base64()).unwrap(), hash);
+    }
+
+    #[test]
+    fn from_hex_rejects_malformed_input() {
+        assert_eq!(SHA256::from_hex("not hex at all").unwrap_err(), DecodeError::InvalidHex);
+        // Odd length.
+        assert_eq!(SHA256::from_hex("abc").unwrap_err(), DecodeError::InvalidHex);
+        // Multi-byte UTF-8 straddling a 2-byte window; passes a byte-length
+        // check but must not panic on non-char-boundary slicing.
+        assert_eq!(
+            SHA256::from_hex(&"é".repeat(32)).unwrap_err(),
+            DecodeError::InvalidHex
+        );
+    }
+
+    #[test]
+    fn from_hex_and_from_base64_reject_wrong_length() {
+        assert_eq!(SHA256::from_hex("aabb").unwrap_err(), DecodeError::InvalidLength);
+        assert_eq!(SHA256::from_base64("YWJj").unwrap_err(), DecodeError::InvalidLength);
+    }
+
+    #[test]
+    fn better_merkle_tree_state_roundtrips_through_bytes() {
+        let mut tree = BetterMerkleTreeSHA256::new();
+        for v in ["a", "b", "c"].iter() {
+            tree.root(SHA256::hash_leaf(v.as_bytes()));
+        }
+
+        let bytes = tree.to_bytes();
+        let restored = BetterMerkleTreeSHA256::from_bytes(&bytes).unwrap();
+        assert_eq!(tree.helper(), restored.helper());
+        assert_eq!(tree.now(), restored.now());
+    }
 }
@@ -0,0 +1,196 @@
+//! Inclusion proofs for `MerkleTree`.
+
+use crate::codec::{self, Cursor, DecodeError};
+use crate::{Hash, MerkleTree};
+
+/// Which side of the running hash a sibling sits on.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// An authentication path from a leaf up to the root.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MerkleProof<H: Hash> {
+    siblings: Vec<(H, Side)>,
+}
+
+impl<H: Hash> MerkleProof<H> {
+    /// Recompute the root from `leaf` and compare it against `root`.
+    pub fn verify(&self, leaf: H, index: usize, root: &H, total_leaves: usize) -> bool {
+        if total_leaves == 0 || index >= total_leaves {
+            return false;
+        }
+
+        let mut hash = leaf;
+        for (sibling, side) in &self.siblings {
+            hash = match side {
+                Side::Left => H::hash_nodes(sibling, &hash),
+                Side::Right => H::hash_nodes(&hash, sibling),
+            };
+        }
+
+        &hash == root
+    }
+
+    /// Compact binary encoding: `[count: u64 LE] ([side: u8][len: u32 LE][bytes])*`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(self.siblings.len() as u64).to_le_bytes());
+        for (sibling, side) in &self.siblings {
+            out.push(match side {
+                Side::Left => 0,
+                Side::Right => 1,
+            });
+            let bytes = sibling.to_bytes();
+            out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            out.extend_from_slice(&bytes);
+        }
+        out
+    }
+
+    /// Inverse of `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut cursor = Cursor::new(bytes);
+        let siblings = codec::decode_items(&mut cursor, |cursor| {
+            let side = match cursor.take_u8()? {
+                0 => Side::Left,
+                1 => Side::Right,
+                _ => return Err(DecodeError::InvalidLength),
+            };
+            Ok((codec::decode_hash(cursor)?, side))
+        })?;
+
+        Ok(MerkleProof { siblings })
+    }
+}
+
+impl<H: Hash> MerkleTree<H> {
+    /// Build the authentication path for the leaf at `index`, mirroring the
+    /// odd-node duplication rule used by `root`. Returns `None` if `hashes`
+    /// is empty or `index` is out of range, mirroring `MerkleProof::verify`'s
+    /// handling of the same case.
+    pub fn proof(mut hashes: Vec<H>, mut index: usize) -> Option<MerkleProof<H>> {
+        if hashes.is_empty() || index >= hashes.len() {
+            return None;
+        }
+
+        let mut siblings = vec![];
+
+        while hashes.len() > 1 {
+            let len = hashes.len();
+            if len % 2 == 1 {
+                let last = hashes[len - 1].clone();
+                hashes.push(last);
+            }
+
+            let is_left = index.is_multiple_of(2);
+            let sibling_index = if is_left { index + 1 } else { index - 1 };
+            let side = if is_left { Side::Right } else { Side::Left };
+            siblings.push((hashes[sibling_index].clone(), side));
+
+            let mut next = vec![];
+            for i in 0..hashes.len() / 2 {
+                next.push(H::hash_nodes(&hashes[i * 2], &hashes[i * 2 + 1]));
+            }
+
+            hashes = next;
+            index /= 2;
+        }
+
+        Some(MerkleProof { siblings })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SHA256;
+
+    fn hashed_list(list: &[&str]) -> Vec<SHA256> {
+        list.iter().map(|v| SHA256::hash_leaf(v.as_bytes())).collect()
+    }
+
+    #[test]
+    fn proof_verifies_every_leaf_even_count() {
+        let list = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        let hashes = hashed_list(&list);
+        let root = MerkleTree::root(hashes.clone());
+
+        for (index, leaf) in hashes.iter().enumerate() {
+            let proof = MerkleTree::proof(hashes.clone(), index).unwrap();
+            assert!(proof.verify(leaf.clone(), index, &root, hashes.len()));
+        }
+    }
+
+    #[test]
+    fn proof_verifies_every_leaf_odd_count() {
+        let list = ["a", "b", "c", "d", "e"];
+        let hashes = hashed_list(&list);
+        let root = MerkleTree::root(hashes.clone());
+
+        for (index, leaf) in hashes.iter().enumerate() {
+            let proof = MerkleTree::proof(hashes.clone(), index).unwrap();
+            assert!(proof.verify(leaf.clone(), index, &root, hashes.len()));
+        }
+    }
+
+    #[test]
+    fn proof_rejects_wrong_leaf() {
+        let list = ["a", "b", "c", "d", "e"];
+        let hashes = hashed_list(&list);
+        let root = MerkleTree::root(hashes.clone());
+
+        let proof = MerkleTree::proof(hashes.clone(), 1).unwrap();
+        assert!(!proof.verify(hashes[0].clone(), 1, &root, hashes.len()));
+    }
+
+    #[test]
+    fn proof_roundtrips_through_bytes() {
+        let list = ["a", "b", "c", "d", "e"];
+        let hashes = hashed_list(&list);
+        let root = MerkleTree::root(hashes.clone());
+
+        let proof = MerkleTree::proof(hashes.clone(), 2).unwrap();
+        let restored = MerkleProof::from_bytes(&proof.to_bytes()).unwrap();
+
+        assert_eq!(proof, restored);
+        assert!(restored.verify(hashes[2].clone(), 2, &root, hashes.len()));
+    }
+
+    #[test]
+    fn proof_returns_none_for_out_of_range_index() {
+        let list = ["a", "b", "c"];
+        let hashes = hashed_list(&list);
+
+        assert!(MerkleTree::proof(hashes.clone(), hashes.len()).is_none());
+        assert!(MerkleTree::proof(hashes, 10).is_none());
+        assert!(MerkleTree::<SHA256>::proof(vec![], 0).is_none());
+    }
+
+    #[test]
+    fn from_bytes_rejects_mismatched_sibling_length() {
+        let list = ["a", "b", "c", "d", "e"];
+        let hashes = hashed_list(&list);
+        let proof = MerkleTree::proof(hashes.clone(), 2).unwrap();
+
+        let mut encoded = proof.to_bytes();
+        // Shrink the length prefix of the first sibling from 32 to 4, leaving
+        // the stored bytes untouched.
+        encoded[9..13].copy_from_slice(&4u32.to_le_bytes());
+
+        let result: Result<MerkleProof<SHA256>, _> = MerkleProof::from_bytes(&encoded);
+        assert_eq!(result, Err(DecodeError::InvalidLength));
+    }
+
+    #[test]
+    fn from_bytes_rejects_huge_claimed_count_without_allocating_it() {
+        // Nine bytes: a `count` of 200 million siblings, no sibling data at all.
+        let mut malicious = (200_000_000u64).to_le_bytes().to_vec();
+        malicious.push(0);
+
+        let result: Result<MerkleProof<SHA256>, _> = MerkleProof::from_bytes(&malicious);
+        assert_eq!(result, Err(DecodeError::UnexpectedEnd));
+    }
+}
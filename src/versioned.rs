@@ -0,0 +1,121 @@
+//! Transactional Merkle tree that keeps a full history of committed states.
+
+use crate::{Hash, MerkleTree};
+
+/// Stages leaf inserts/updates and snapshots them into numbered, rollback-able
+/// versions, instead of only exposing the current root.
+pub struct VersionedMerkleTree<H: Hash> {
+    committed: Vec<Vec<H>>,
+    staged: Vec<H>,
+}
+
+impl<H: Hash> VersionedMerkleTree<H> {
+    /// Version 0 is the empty tree, already committed.
+    pub fn new() -> Self {
+        VersionedMerkleTree {
+            committed: vec![vec![]],
+            staged: vec![],
+        }
+    }
+
+    /// Stage a new leaf. Not visible in `root_at` until `commit`.
+    pub fn insert(&mut self, leaf: H) {
+        self.staged.push(leaf);
+    }
+
+    /// Stage replacing the leaf at `index`. Not visible in `root_at` until
+    /// `commit`.
+    pub fn update(&mut self, index: usize, leaf: H) {
+        self.staged[index] = leaf;
+    }
+
+    /// Snapshot the staged leaves as a new version and return its root.
+    pub fn commit(&mut self) -> H {
+        self.committed.push(self.staged.clone());
+        self.root()
+    }
+
+    /// Discard staged changes, reverting to the latest committed version.
+    pub fn rollback(&mut self) {
+        self.staged = self.committed.last().expect("version 0 always exists").clone();
+    }
+
+    /// Discard staged changes and restore the leaves as of `version`.
+    pub fn rollback_to(&mut self, version: usize) {
+        self.staged = self.committed[version].clone();
+    }
+
+    /// Number of the most recent commit (0 is the empty, always-committed
+    /// starting version).
+    pub fn version(&self) -> usize {
+        self.committed.len() - 1
+    }
+
+    /// Root of the leaves as they were committed at `version`.
+    pub fn root_at(&self, version: usize) -> H {
+        MerkleTree::root(self.committed[version].clone())
+    }
+
+    /// Root of the current staged leaves, including uncommitted changes.
+    pub fn root(&self) -> H {
+        MerkleTree::root(self.staged.clone())
+    }
+}
+
+impl<H: Hash> Default for VersionedMerkleTree<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SHA256;
+
+    #[test]
+    fn commit_and_rollback_to_prior_version() {
+        let mut tree = VersionedMerkleTree::<SHA256>::new();
+        let v0_root = tree.root_at(0);
+
+        tree.insert(SHA256::hash_leaf(b"a"));
+        tree.insert(SHA256::hash_leaf(b"b"));
+        let v1_root = tree.commit();
+        assert_eq!(tree.version(), 1);
+
+        tree.insert(SHA256::hash_leaf(b"c"));
+        let v2_root = tree.commit();
+        assert_eq!(tree.version(), 2);
+        assert_ne!(v1_root, v2_root);
+
+        tree.rollback_to(1);
+        assert_eq!(tree.root(), v1_root);
+        assert_eq!(tree.root_at(0), v0_root);
+        assert_eq!(tree.root_at(2), v2_root);
+    }
+
+    #[test]
+    fn rollback_discards_uncommitted_changes() {
+        let mut tree = VersionedMerkleTree::<SHA256>::new();
+        tree.insert(SHA256::hash_leaf(b"a"));
+        let committed_root = tree.commit();
+
+        tree.insert(SHA256::hash_leaf(b"b"));
+        assert_ne!(tree.root(), committed_root);
+
+        tree.rollback();
+        assert_eq!(tree.root(), committed_root);
+    }
+
+    #[test]
+    fn update_replaces_a_staged_leaf() {
+        let mut tree = VersionedMerkleTree::<SHA256>::new();
+        tree.insert(SHA256::hash_leaf(b"a"));
+        tree.commit();
+
+        tree.update(0, SHA256::hash_leaf(b"z"));
+        let updated_root = tree.commit();
+
+        assert_eq!(updated_root, MerkleTree::root(vec![SHA256::hash_leaf(b"z")]));
+    }
+}